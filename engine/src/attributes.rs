@@ -0,0 +1,100 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Error, Result};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, ForeignItem, Item, ItemMod, Path, Token};
+
+/// Attributes which cxx accepts on items within the bridge module.
+const SUPPORTED_ATTRIBUTES: &[&str] = &["doc", "derive", "cxx_name", "rust_name", "namespace"];
+
+/// Traits which cxx can derive for shared structs.
+const SUPPORTED_DERIVES: &[&str] = &["Clone", "Copy"];
+
+/// Attributes requested by the user for a given generated item.
+pub(crate) struct ExtraAttributes {
+    pub(crate) target: String,
+    pub(crate) attrs: Vec<Attribute>,
+}
+
+/// Appends the requested attributes to the relevant items in the bridge
+/// module. It's an error to ask for attributes on an item which wasn't
+/// generated, since otherwise typos would silently do nothing.
+pub(crate) fn add_attributes(bridge: &mut ItemMod, extra: &[ExtraAttributes]) -> Result<()> {
+    let items = match &mut bridge.content {
+        Some((_, items)) => items,
+        None => return Ok(()),
+    };
+    for ea in extra {
+        let (attrs, is_struct) = item_attrs_mut(items, &ea.target)
+            .ok_or_else(|| Error::AttributeTargetNotFound(ea.target.clone()))?;
+        // cxx only derives for shared structs.
+        if !is_struct && ea.attrs.iter().any(|attr| attr.path.is_ident("derive")) {
+            return Err(Error::DeriveTargetNotStruct(ea.target.clone()));
+        }
+        attrs.extend(ea.attrs.iter().cloned());
+    }
+    Ok(())
+}
+
+/// Checks an attribute requested by the user is one which cxx will
+/// accept. Others would be rejected by cxx, or silently dropped.
+pub(crate) fn validate(attr: &Attribute) -> syn::Result<()> {
+    if !SUPPORTED_ATTRIBUTES
+        .iter()
+        .any(|name| attr.path.is_ident(name))
+    {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "cxx only supports doc, derive, cxx_name, rust_name and namespace attributes",
+        ));
+    }
+    if attr.path.is_ident("derive") {
+        let derives = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+        for derive in &derives {
+            if !SUPPORTED_DERIVES.iter().any(|name| derive.is_ident(name)) {
+                return Err(syn::Error::new_spanned(
+                    derive,
+                    "cxx can only derive Clone and Copy",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds the attributes of the named item, and whether it's a struct.
+fn item_attrs_mut<'a>(items: &'a mut [Item], name: &str) -> Option<(&'a mut Vec<Attribute>, bool)> {
+    for item in items {
+        match item {
+            Item::Struct(s) if s.ident == name => return Some((&mut s.attrs, true)),
+            Item::Enum(e) if e.ident == name => return Some((&mut e.attrs, false)),
+            Item::ForeignMod(fm) => {
+                for fi in &mut fm.items {
+                    match fi {
+                        ForeignItem::Fn(f) if f.sig.ident == name => {
+                            return Some((&mut f.attrs, false))
+                        }
+                        ForeignItem::Type(t) if t.ident == name => {
+                            return Some((&mut t.attrs, false))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,51 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for walking the `cxx::bridge` module which bindgen generates.
+
+use syn::{ForeignItem, ForeignItemFn, Item, ItemMod};
+
+/// All the functions declared in `extern` blocks within the bridge module.
+pub(crate) fn foreign_fns(bridge: &ItemMod) -> impl Iterator<Item = &ForeignItemFn> {
+    bridge
+        .content
+        .iter()
+        .flat_map(|(_, items)| items.iter())
+        .filter_map(|item| match item {
+            Item::ForeignMod(fm) => Some(fm.items.iter()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|item| match item {
+            ForeignItem::Fn(f) => Some(f),
+            _ => None,
+        })
+}
+
+/// Mutable version of `foreign_fns`.
+pub(crate) fn foreign_fns_mut(bridge: &mut ItemMod) -> impl Iterator<Item = &mut ForeignItemFn> {
+    bridge
+        .content
+        .iter_mut()
+        .flat_map(|(_, items)| items.iter_mut())
+        .filter_map(|item| match item {
+            Item::ForeignMod(fm) => Some(fm.items.iter_mut()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|item| match item {
+            ForeignItem::Fn(f) => Some(f),
+            _ => None,
+        })
+}
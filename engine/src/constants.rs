@@ -0,0 +1,96 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Constants, such as those bindgen generates from `#define`s. cxx
+//! doesn't accept these within the bridge module, so they're moved out.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{parse_quote, Expr, ForeignItem, Ident, Item, ItemConst, ItemMod, Lit, Visibility};
+
+/// Removes all constants from the bridge module. C strings, which
+/// bindgen gives as NUL-terminated byte strings, become `&str`.
+/// Global variables are dropped: the same bindgen option which finds
+/// constants finds them too, but cxx rejects them and nothing yet
+/// generates accessors for them.
+pub(crate) fn take_constants(bridge: &mut ItemMod) -> Vec<ItemConst> {
+    let items = match &mut bridge.content {
+        Some((_, items)) => items,
+        None => return Vec::new(),
+    };
+    let (constants, mut others): (Vec<Item>, Vec<Item>) = items
+        .drain(..)
+        .filter(|item| !matches!(item, Item::Static(_)))
+        .partition(|item| matches!(item, Item::Const(_)));
+    for item in &mut others {
+        if let Item::ForeignMod(fm) = item {
+            fm.items.retain(|fi| !matches!(fi, ForeignItem::Static(_)));
+        }
+    }
+    // Don't leave behind extern blocks which only held globals.
+    others.retain(|item| match item {
+        Item::ForeignMod(fm) => !fm.items.is_empty(),
+        _ => true,
+    });
+    *items = others;
+    constants
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Const(c) => Some(convert_c_string(c)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn convert_c_string(mut c: ItemConst) -> ItemConst {
+    let s = match &*c.expr {
+        Expr::Lit(el) => match &el.lit {
+            Lit::ByteStr(bs) => match bs.value().split_last() {
+                Some((0, chars)) => String::from_utf8(chars.to_vec()).ok(),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(s) = s {
+        *c.ty = parse_quote! { &str };
+        *c.expr = parse_quote! { #s };
+    }
+    c
+}
+
+/// Emits the bridge module along with any constants taken from it.
+/// If there are some, the bridge nests within a module of the original
+/// name, which re-exports its contents, so callers see no difference.
+pub(crate) fn generate_bridge_with_constants(
+    bridge: ItemMod,
+    constants: Vec<ItemConst>,
+) -> TokenStream2 {
+    if constants.is_empty() {
+        return bridge.into_token_stream();
+    }
+    let vis = bridge.vis.clone();
+    let mod_name = bridge.ident.clone();
+    let mut inner = bridge;
+    inner.vis = Visibility::Inherited;
+    inner.ident = Ident::new("bridge", mod_name.span());
+    quote! {
+        #vis mod #mod_name {
+            #inner
+            pub use self::bridge::*;
+            #(#constants)*
+        }
+    }
+}
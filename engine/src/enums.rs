@@ -0,0 +1,165 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Error, Result};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Expr, Ident, Item, ItemEnum, ItemMod, Lit, Variant};
+
+/// Generates impls for each enum in the bridge module: conversions to
+/// and from `u32`, plus bitwise operators for enums which are used as
+/// sets of flags. Those are the ones named in `bitflags`, or those
+/// whose values all look like flags. Naming an enum in `bitflags` which
+/// doesn't exist is an error.
+/// cxx represents shared enums as a struct with a `repr` field and an
+/// associated constant per variant, so any integer can be smuggled in;
+/// `TryFrom` only accepts the declared values. Enums with values beyond
+/// the range of `u32` don't get conversions, rather than truncating.
+/// The output expects the contents of the bridge module to be in scope.
+pub(crate) fn generate_enum_impls(bridge: &ItemMod, bitflags: &[String]) -> Result<TokenStream2> {
+    let enums: Vec<&ItemEnum> = bridge
+        .content
+        .iter()
+        .flat_map(|(_, items)| items.iter())
+        .filter_map(|item| match item {
+            Item::Enum(e) => Some(e),
+            _ => None,
+        })
+        .collect();
+    if let Some(name) = bitflags
+        .iter()
+        .find(|name| !enums.iter().any(|e| e.ident == name))
+    {
+        return Err(Error::BitflagsTargetNotFound(name.clone()));
+    }
+    let mut ts = TokenStream2::new();
+    for e in enums {
+        if fits_u32(e) {
+            ts.extend(generate_conversions(e));
+        }
+        if bitflags.iter().any(|name| e.ident == name) || looks_like_flags(e) {
+            ts.extend(generate_flag_operators(e));
+        }
+    }
+    Ok(ts)
+}
+
+fn generate_conversions(e: &ItemEnum) -> TokenStream2 {
+    let name = &e.ident;
+    let checks = e.variants.iter().map(|v| {
+        let variant = &v.ident;
+        quote! {
+            if value == #name::#variant.repr as u32 {
+                return Ok(#name::#variant);
+            }
+        }
+    });
+    quote! {
+        impl std::convert::TryFrom<u32> for #name {
+            type Error = u32;
+            fn try_from(value: u32) -> std::result::Result<Self, u32> {
+                #(#checks)*
+                Err(value)
+            }
+        }
+        impl From<#name> for u32 {
+            fn from(value: #name) -> u32 {
+                value.repr as u32
+            }
+        }
+    }
+}
+
+/// Integer types too wide for a `u32` to hold all their values.
+const WIDE_REPRS: &[&str] = &["u64", "i64", "u128", "i128", "usize", "isize"];
+
+/// Whether every value of the enum, declared or not, fits in a `u32`.
+fn fits_u32(e: &ItemEnum) -> bool {
+    let wide_repr = e.attrs.iter().filter(|a| a.path.is_ident("repr")).any(|a| {
+        a.parse_args::<Ident>()
+            .map(|ty| WIDE_REPRS.iter().any(|wide| ty == wide))
+            .unwrap_or(false)
+    });
+    if wide_repr {
+        return false;
+    }
+    let mut next = 0u64;
+    for v in &e.variants {
+        let value = match &v.discriminant {
+            Some(_) => match discriminant(v) {
+                Some(value) => value,
+                // Negative, or not a plain literal.
+                None => return false,
+            },
+            None => next,
+        };
+        if value > u64::from(u32::MAX) {
+            return false;
+        }
+        next = value + 1;
+    }
+    true
+}
+
+fn discriminant(v: &Variant) -> Option<u64> {
+    match &v.discriminant {
+        Some((_, Expr::Lit(el))) => match &el.lit {
+            Lit::Int(i) => i.base10_parse::<u64>().ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether every value is zero or a power of two. We also insist on
+/// a value of at least 4, else any enum numbered 0, 1, 2 would qualify.
+fn looks_like_flags(e: &ItemEnum) -> bool {
+    let mut max = 0;
+    for v in &e.variants {
+        match discriminant(v) {
+            Some(value) if value == 0 || value.is_power_of_two() => max = max.max(value),
+            _ => return false,
+        }
+    }
+    max >= 4
+}
+
+fn generate_flag_operators(e: &ItemEnum) -> TokenStream2 {
+    let name = &e.ident;
+    quote! {
+        impl std::ops::BitOr for #name {
+            type Output = Self;
+            fn bitor(self, rhs: Self) -> Self {
+                #name { repr: self.repr | rhs.repr }
+            }
+        }
+        impl std::ops::BitAnd for #name {
+            type Output = Self;
+            fn bitand(self, rhs: Self) -> Self {
+                #name { repr: self.repr & rhs.repr }
+            }
+        }
+        impl std::ops::BitOrAssign for #name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.repr |= rhs.repr;
+            }
+        }
+        impl #name {
+            /// Whether all the flags set in `other` are also set in `self`.
+            pub fn contains(self, other: Self) -> bool {
+                self.repr & other.repr == other.repr
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bridge::foreign_fns_mut;
+use crate::fn_pattern::{find_unmatched, FnPattern};
+use crate::{Error, Result};
+use syn::{parse_quote, ItemMod, ReturnType, Type};
+
+/// Makes functions whose names match any of `patterns` return a `Result`.
+/// cxx then catches any C++ exception escaping the call and hands it
+/// back as a `cxx::Exception`, instead of it unwinding into Rust.
+/// Patterns are globs rather than regexes, like the other directives
+/// naming functions, so `*` is the only special character.
+pub(crate) fn catch_exceptions(bridge: &mut ItemMod, patterns: &[FnPattern]) -> Result<()> {
+    if let Some(glob) = find_unmatched(bridge, patterns) {
+        return Err(Error::CatchExceptionsTargetNotFound(glob.to_string()));
+    }
+    for f in foreign_fns_mut(bridge) {
+        let name = f.sig.ident.to_string();
+        if !patterns.iter().any(|pattern| pattern.is_match(&name)) {
+            continue;
+        }
+        let output = match &f.sig.output {
+            ReturnType::Default => parse_quote! { -> Result<()> },
+            ReturnType::Type(_, ty) if is_result(ty) => continue,
+            ReturnType::Type(_, ty) => parse_quote! { -> Result<#ty> },
+        };
+        f.sig.output = output;
+    }
+    Ok(())
+}
+
+fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(tp) => tp
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident == "Result")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
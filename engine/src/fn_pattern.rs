@@ -0,0 +1,60 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Globs which pick out functions in the bridge module, for directives
+//! such as `CatchExceptions`.
+
+use crate::bridge::foreign_fns;
+use crate::glob_to_regex;
+use regex::Regex;
+use syn::{ItemMod, LitStr};
+
+/// A glob matched against whole function names.
+pub(crate) struct FnPattern {
+    glob: String,
+    re: Regex,
+}
+
+impl FnPattern {
+    /// Namespaces don't survive into the bridge module, so a pattern
+    /// containing `::` could never match and is rejected up front.
+    pub(crate) fn parse(glob: &LitStr) -> syn::Result<Self> {
+        let value = glob.value();
+        if value.contains("::") {
+            return Err(syn::Error::new(
+                glob.span(),
+                "expected a function name without its namespace",
+            ));
+        }
+        // Anchored, to match bindgen's handling of the allowlist.
+        let re = Regex::new(&format!("^({})$", glob_to_regex(&value))).unwrap();
+        Ok(FnPattern { glob: value, re })
+    }
+
+    pub(crate) fn is_match(&self, name: &str) -> bool {
+        self.re.is_match(name)
+    }
+}
+
+/// Finds the first of `patterns` which matches no function in the bridge
+/// module, since that's most likely a typo.
+pub(crate) fn find_unmatched<'a>(bridge: &ItemMod, patterns: &'a [FnPattern]) -> Option<&'a str> {
+    let names: Vec<String> = foreign_fns(bridge)
+        .map(|f| f.sig.ident.to_string())
+        .collect();
+    patterns
+        .iter()
+        .find(|pattern| !names.iter().any(|name| pattern.is_match(name)))
+        .map(|pattern| pattern.glob.as_str())
+}
@@ -14,10 +14,20 @@
 
 #![feature(proc_macro_span)]
 
+mod attributes;
+mod bridge;
+mod constants;
+mod enums;
+mod exceptions;
+mod fn_pattern;
+mod methods;
+mod renames;
+mod unsafe_policy;
+
 use proc_macro2::TokenStream as TokenStream2;
 use std::path::PathBuf;
 
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use syn::parse::{Parse, ParseStream, Result as ParseResult};
 
 use cxx_gen::GeneratedCode;
@@ -25,6 +35,13 @@ use syn::{ItemMod, Macro};
 
 use log::debug;
 use osstrtools::OsStrTools;
+use regex::Regex;
+
+use attributes::ExtraAttributes;
+use fn_pattern::FnPattern;
+use methods::MethodPrefix;
+use renames::Rename;
+pub use unsafe_policy::UnsafePolicy;
 
 #[derive(Debug)]
 pub enum Error {
@@ -34,6 +51,16 @@ pub enum Error {
     Parsing(syn::Error),
     NoAutoCxxInc,
     CouldNotCanoncalizeIncludeDir(PathBuf),
+    AttributeTargetNotFound(String),
+    DeriveTargetNotStruct(String),
+    RenameTargetNotFound(String),
+    RenameOfTypeNotSupported(String),
+    CatchExceptionsTargetNotFound(String),
+    BitflagsTargetNotFound(String),
+    UnsafeFnTargetNotFound(String),
+    SafeFnTargetNotFound(String),
+    MethodPrefixTargetNotFound(String),
+    DuplicateMethod(String),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -43,6 +70,12 @@ pub enum CppInclusion {
     Header(String),
 }
 
+/// Converts a pattern where `*` matches anything into the regex syntax
+/// which bindgen expects for its allowlist.
+fn glob_to_regex(glob: &str) -> String {
+    regex::escape(glob).replace("\\*", ".*")
+}
+
 /// Core of the autocxx engine.
 /// TODO - consider merging this 'engine' sub-crate with the main crate.
 /// TODO - consider whether this 'engine' crate should actually be a
@@ -51,6 +84,18 @@ pub enum CppInclusion {
 pub struct IncludeCpp {
     inclusions: Vec<CppInclusion>,
     allowlist: Vec<String>,
+    const_allowlist: Vec<String>,
+    blocklist: Vec<String>,
+    opaque_types: Vec<String>,
+    unsafe_policy: UnsafePolicy,
+    unsafe_fns: Vec<FnPattern>,
+    safe_fns: Vec<FnPattern>,
+    method_prefixes: Vec<MethodPrefix>,
+    catch_exceptions: Vec<FnPattern>,
+    extra_attributes: Vec<ExtraAttributes>,
+    mod_name: Option<syn::Ident>,
+    bitflags: Vec<String>,
+    renames: Vec<Rename>,
 }
 
 impl Parse for IncludeCpp {
@@ -68,6 +113,18 @@ impl IncludeCpp {
 
         let mut inclusions = Vec::new();
         let mut allowlist = Vec::new();
+        let mut const_allowlist = Vec::new();
+        let mut blocklist = Vec::new();
+        let mut opaque_types = Vec::new();
+        let mut unsafe_policy = UnsafePolicy::default();
+        let mut unsafe_fns = Vec::new();
+        let mut safe_fns = Vec::new();
+        let mut method_prefixes = Vec::new();
+        let mut catch_exceptions = Vec::new();
+        let mut extra_attributes = Vec::new();
+        let mut mod_name = None;
+        let mut bitflags = Vec::new();
+        let mut renames = Vec::new();
 
         while !input.is_empty() {
             let ident: syn::Ident = input.parse()?;
@@ -80,9 +137,112 @@ impl IncludeCpp {
                 let args;
                 syn::parenthesized!(args in input);
                 let allow: syn::LitStr = args.parse()?;
+                Regex::new(&allow.value()).map_err(|e| syn::Error::new(allow.span(), e))?;
                 allowlist.push(allow.value());
+            } else if ident == "AllowGlob" {
+                let args;
+                syn::parenthesized!(args in input);
+                let allow: syn::LitStr = args.parse()?;
+                allowlist.push(glob_to_regex(&allow.value()));
+            } else if ident == "AllowConst" {
+                let args;
+                syn::parenthesized!(args in input);
+                let allow: syn::LitStr = args.parse()?;
+                const_allowlist.push(glob_to_regex(&allow.value()));
+            } else if ident == "Block" {
+                let args;
+                syn::parenthesized!(args in input);
+                let block: syn::LitStr = args.parse()?;
+                blocklist.push(glob_to_regex(&block.value()));
+            } else if ident == "Opaque" {
+                let args;
+                syn::parenthesized!(args in input);
+                let opaque: syn::LitStr = args.parse()?;
+                opaque_types.push(glob_to_regex(&opaque.value()));
+            } else if ident == "UnsafePolicy" {
+                let args;
+                syn::parenthesized!(args in input);
+                let policy: syn::LitStr = args.parse()?;
+                unsafe_policy = match policy.value().as_str() {
+                    "all_safe" => UnsafePolicy::AllFunctionsSafe,
+                    "all_unsafe" => UnsafePolicy::AllFunctionsUnsafe,
+                    "raw_pointers" => UnsafePolicy::RawPointersUnsafe,
+                    _ => {
+                        return Err(syn::Error::new(
+                            policy.span(),
+                            "expected all_safe, all_unsafe or raw_pointers",
+                        ))
+                    }
+                };
+            } else if ident == "MethodPrefix" {
+                let args;
+                syn::parenthesized!(args in input);
+                let type_name: syn::LitStr = args.parse()?;
+                args.parse::<syn::Token![,]>()?;
+                let prefix: syn::LitStr = args.parse()?;
+                method_prefixes.push(MethodPrefix {
+                    type_name: type_name.parse()?,
+                    prefix: prefix.value(),
+                });
+            } else if ident == "CatchExceptions" {
+                let args;
+                syn::parenthesized!(args in input);
+                let pattern: syn::LitStr = args.parse()?;
+                catch_exceptions.push(FnPattern::parse(&pattern)?);
+            } else if ident == "UnsafeFn" {
+                let args;
+                syn::parenthesized!(args in input);
+                let pattern: syn::LitStr = args.parse()?;
+                unsafe_fns.push(FnPattern::parse(&pattern)?);
+            } else if ident == "SafeFn" {
+                let args;
+                syn::parenthesized!(args in input);
+                let pattern: syn::LitStr = args.parse()?;
+                safe_fns.push(FnPattern::parse(&pattern)?);
+            } else if ident == "Attr" {
+                let args;
+                syn::parenthesized!(args in input);
+                let target: syn::LitStr = args.parse()?;
+                let mut attrs = Vec::new();
+                while !args.is_empty() {
+                    args.parse::<syn::Token![,]>()?;
+                    let attr: syn::LitStr = args.parse()?;
+                    for attr in attr.parse_with(syn::Attribute::parse_outer)? {
+                        attributes::validate(&attr)?;
+                        attrs.push(attr);
+                    }
+                }
+                extra_attributes.push(ExtraAttributes {
+                    target: target.value(),
+                    attrs,
+                });
+            } else if ident == "ModName" {
+                let args;
+                syn::parenthesized!(args in input);
+                let name: syn::LitStr = args.parse()?;
+                mod_name = Some(name.parse()?);
+            } else if ident == "Bitflags" {
+                let args;
+                syn::parenthesized!(args in input);
+                let name: syn::LitStr = args.parse()?;
+                bitflags.push(name.value());
+            } else if ident == "Rename" {
+                let args;
+                syn::parenthesized!(args in input);
+                let from: syn::LitStr = args.parse()?;
+                args.parse::<syn::Token![,]>()?;
+                let to: syn::LitStr = args.parse()?;
+                renames.push(Rename {
+                    from: from.value(),
+                    to: to.parse()?,
+                });
             } else {
-                return Err(syn::Error::new(ident.span(), "expected Header or Allow"));
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "expected Header, Allow, AllowGlob, AllowConst, Block, Opaque, UnsafePolicy, \
+                     UnsafeFn, SafeFn, MethodPrefix, CatchExceptions, Attr, ModName, Bitflags \
+                     or Rename",
+                ));
             }
             if input.is_empty() {
                 break;
@@ -93,6 +253,18 @@ impl IncludeCpp {
         Ok(IncludeCpp {
             inclusions,
             allowlist,
+            const_allowlist,
+            blocklist,
+            opaque_types,
+            unsafe_policy,
+            unsafe_fns,
+            safe_fns,
+            method_prefixes,
+            catch_exceptions,
+            extra_attributes,
+            mod_name,
+            bitflags,
+            renames,
         })
     }
 
@@ -156,10 +328,25 @@ impl IncludeCpp {
             builder = builder.whitelist_type(a);
             builder = builder.whitelist_function(a);
         }
+        // Vars include constants from #defines.
+        for c in &self.const_allowlist {
+            builder = builder.whitelist_var(c);
+        }
+        for b in &self.blocklist {
+            builder = builder.blacklist_function(b);
+        }
+        // Opaque types are exposed without their fields, even if they could
+        // otherwise be passed by value. Their layout is still shared with
+        // C++: bindgen replaces the fields with a `_bindgen_opaque_blob` of
+        // the same size and alignment, so the type remains a POD to cxx.
+        for o in &self.opaque_types {
+            builder = builder.opaque_type(o);
+        }
         Ok(builder)
     }
 
-    pub fn generate_rs(self) -> Result<TokenStream2> {
+    /// Generates the `cxx::bridge` module itself.
+    fn generate_bridge(&self) -> Result<ItemMod> {
         // TODO:
         // 4. (also respects environment variables to pick up more headers,
         //     include paths and #defines)
@@ -173,15 +360,57 @@ impl IncludeCpp {
         // TODO see what that type is and whether we can avoid reparsing.
         let bindings = bindings.to_string();
         debug!("Bindings: {}", bindings);
-        let bindings = syn::parse_str::<ItemMod>(&bindings).map_err(Error::Parsing)?;
-        let mut ts = TokenStream2::new();
-        bindings.to_tokens(&mut ts);
+        let mut bindings = syn::parse_str::<ItemMod>(&bindings).map_err(Error::Parsing)?;
+        if let Some(mod_name) = &self.mod_name {
+            bindings.ident = mod_name.clone();
+        }
+        self.unsafe_policy
+            .apply(&mut bindings, &self.unsafe_fns, &self.safe_fns)?;
+        exceptions::catch_exceptions(&mut bindings, &self.catch_exceptions)?;
+        attributes::add_attributes(&mut bindings, &self.extra_attributes)?;
+        // Last, since the other directives refer to items by their C++ names.
+        // MethodPrefix runs later, but finds those via #[cxx_name].
+        renames::apply_renames(&mut bindings, &self.renames)?;
+        Ok(bindings)
+    }
+
+    /// Generates additional Rust items which live alongside the
+    /// `cxx::bridge` module. These are pure Rust, so don't need to
+    /// be seen by the C++ code generator.
+    fn generate_extras(&self, bridge: &ItemMod) -> Result<TokenStream2> {
+        let mut extras = methods::generate_methods(bridge, &self.method_prefixes)?;
+        extras.extend(enums::generate_enum_impls(bridge, &self.bitflags)?);
+        if extras.is_empty() {
+            return Ok(TokenStream2::new());
+        }
+        let mod_name = &bridge.ident;
+        // Signatures copied from the bridge may use cxx's Result, whose
+        // error type is implicit there.
+        Ok(quote! {
+            #[allow(unused_imports, dead_code)]
+            const _: () = {
+                use cxx::{CxxString, CxxVector, UniquePtr};
+                use std::pin::Pin;
+                use #mod_name::*;
+                type Result<T> = std::result::Result<T, cxx::Exception>;
+                #extras
+            };
+        })
+    }
+
+    pub fn generate_rs(self) -> Result<TokenStream2> {
+        let mut bridge = self.generate_bridge()?;
+        let constants = constants::take_constants(&mut bridge);
+        let extras = self.generate_extras(&bridge)?;
+        let mut ts = constants::generate_bridge_with_constants(bridge, constants);
+        ts.extend(extras);
         Ok(ts)
     }
 
     pub fn generate_h_and_cxx(self) -> Result<GeneratedCode> {
-        let rs = self.generate_rs()?;
-        cxx_gen::generate_header_and_cc(rs).map_err(Error::CxxGen)
+        let mut bridge = self.generate_bridge()?;
+        constants::take_constants(&mut bridge);
+        cxx_gen::generate_header_and_cc(bridge.into_token_stream()).map_err(Error::CxxGen)
     }
 
     pub fn include_dirs(&self) -> Result<Vec<PathBuf>> {
@@ -0,0 +1,124 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exposes C-style free functions such as `widget_set_name(Widget*, ...)`
+//! as methods on the type they operate upon.
+
+use crate::bridge::foreign_fns;
+use crate::renames::cxx_name;
+use crate::{Error, Result};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::collections::HashSet;
+use syn::{FnArg, ForeignItemFn, Ident, ItemMod, Type};
+
+/// Free functions whose names start with `prefix`, and whose first
+/// parameter is a pointer or reference to `type_name`, become methods
+/// on that type with the prefix stripped. Names are the C++ ones, even
+/// for functions which have been renamed.
+pub(crate) struct MethodPrefix {
+    pub(crate) type_name: Ident,
+    pub(crate) prefix: String,
+}
+
+/// Generates `impl` blocks for all the functions matching any of the
+/// given prefixes. The output expects the contents of the bridge module
+/// to be in scope. It's an error for a prefix to produce no methods, or
+/// for two functions to produce the same method.
+pub(crate) fn generate_methods(
+    bridge: &ItemMod,
+    prefixes: &[MethodPrefix],
+) -> Result<TokenStream2> {
+    let mut ts = TokenStream2::new();
+    let mut seen = HashSet::new();
+    for prefix in prefixes {
+        let mut found = false;
+        for f in foreign_fns(bridge) {
+            if let Some((method_name, method)) = prefix.generate_method(f) {
+                let qualified = format!("{}::{}", prefix.type_name, method_name);
+                if !seen.insert(qualified.clone()) {
+                    return Err(Error::DuplicateMethod(qualified));
+                }
+                ts.extend(method);
+                found = true;
+            }
+        }
+        if !found {
+            return Err(Error::MethodPrefixTargetNotFound(format!(
+                "{}, {}",
+                prefix.type_name, prefix.prefix
+            )));
+        }
+    }
+    Ok(ts)
+}
+
+impl MethodPrefix {
+    fn generate_method(&self, f: &ForeignItemFn) -> Option<(Ident, TokenStream2)> {
+        let fn_name = &f.sig.ident;
+        let method_name = cxx_name(f);
+        let method_name = method_name.strip_prefix(&self.prefix)?;
+        // Leave alone any function where stripping the prefix doesn't
+        // leave an identifier, such as `widget_2d_size`.
+        let mut method_name = syn::parse_str::<Ident>(method_name).ok()?;
+        method_name.set_span(fn_name.span());
+        let mut inputs = f.sig.inputs.iter();
+        let (receiver, self_arg) = match inputs.next()? {
+            FnArg::Typed(pt) => self.receiver_for(&pt.ty)?,
+            FnArg::Receiver(_) => return None,
+        };
+        let mut arg_pats = Vec::new();
+        let mut arg_tys = Vec::new();
+        for input in inputs {
+            match input {
+                FnArg::Typed(pt) => {
+                    arg_pats.push(&pt.pat);
+                    arg_tys.push(&pt.ty);
+                }
+                FnArg::Receiver(_) => return None,
+            }
+        }
+        let type_name = &self.type_name;
+        let unsafety = &f.sig.unsafety;
+        let output = &f.sig.output;
+        let method = quote! {
+            impl #type_name {
+                pub #unsafety fn #method_name(#receiver #(, #arg_pats: #arg_tys)*) #output {
+                    #fn_name(#self_arg #(, #arg_pats)*)
+                }
+            }
+        };
+        Some((method_name, method))
+    }
+
+    /// Works out the receiver for the method, and how to pass it on to
+    /// the underlying function.
+    fn receiver_for(&self, ty: &Type) -> Option<(TokenStream2, TokenStream2)> {
+        let (elem, is_mut, is_ptr) = match ty {
+            Type::Reference(r) => (&r.elem, r.mutability.is_some(), false),
+            Type::Ptr(p) => (&p.elem, p.mutability.is_some(), true),
+            _ => return None,
+        };
+        match elem.as_ref() {
+            Type::Path(tp) if tp.path.is_ident(&self.type_name) => {}
+            _ => return None,
+        }
+        Some(match (is_mut, is_ptr) {
+            (false, false) => (quote! { &self }, quote! { self }),
+            (true, false) => (quote! { &mut self }, quote! { self }),
+            (false, true) => (quote! { &self }, quote! { self as *const Self }),
+            (true, true) => (quote! { &mut self }, quote! { self as *mut Self }),
+        })
+    }
+}
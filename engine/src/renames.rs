@@ -0,0 +1,74 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bridge::foreign_fns_mut;
+use crate::{Error, Result};
+use syn::{parse_quote, ForeignItem, ForeignItemFn, Ident, Item, ItemMod, Lit, Meta};
+
+/// A function which should be known by a different name in Rust.
+pub(crate) struct Rename {
+    pub(crate) from: String,
+    pub(crate) to: Ident,
+}
+
+/// Renames functions in the bridge module, recording the original
+/// name with `#[cxx_name]` so that cxx still calls the right C++.
+/// Only functions can be renamed for now, since types are referred to
+/// throughout the signatures of other items.
+pub(crate) fn apply_renames(bridge: &mut ItemMod, renames: &[Rename]) -> Result<()> {
+    for rename in renames {
+        if is_type(bridge, &rename.from) {
+            return Err(Error::RenameOfTypeNotSupported(rename.from.clone()));
+        }
+        let f = foreign_fns_mut(bridge)
+            .find(|f| f.sig.ident == rename.from)
+            .ok_or_else(|| Error::RenameTargetNotFound(rename.from.clone()))?;
+        let cxx_name = f.sig.ident.to_string();
+        f.attrs.push(parse_quote! { #[cxx_name = #cxx_name] });
+        f.sig.ident = rename.to.clone();
+    }
+    Ok(())
+}
+
+fn is_type(bridge: &ItemMod, name: &str) -> bool {
+    let items = match &bridge.content {
+        Some((_, items)) => items,
+        None => return false,
+    };
+    items.iter().any(|item| match item {
+        Item::Struct(s) => s.ident == name,
+        Item::Enum(e) => e.ident == name,
+        Item::ForeignMod(fm) => fm.items.iter().any(|fi| match fi {
+            ForeignItem::Type(t) => t.ident == name,
+            _ => false,
+        }),
+        _ => false,
+    })
+}
+
+/// The C++ name of a function, which differs from its Rust name if it
+/// has been renamed.
+pub(crate) fn cxx_name(f: &ForeignItemFn) -> String {
+    f.attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cxx_name"))
+        .find_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) => match nv.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap_or_else(|| f.sig.ident.to_string())
+}
@@ -0,0 +1,165 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bridge::foreign_fns_mut;
+use crate::fn_pattern::{find_unmatched, FnPattern};
+use crate::{Error, Result};
+use std::collections::HashMap;
+use syn::{
+    FnArg, ForeignItemFn, GenericArgument, Ident, Item, ItemMod, PathArguments, ReturnType, Type,
+};
+
+/// Which of the generated functions should be marked `unsafe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafePolicy {
+    /// Leave all functions safe to call.
+    AllFunctionsSafe,
+    /// Mark every function as unsafe.
+    AllFunctionsUnsafe,
+    /// Functions are safe unless they take or return raw pointers
+    /// (including function pointers, and typedefs of either), or return
+    /// a reference with nothing to borrow its lifetime from.
+    RawPointersUnsafe,
+}
+
+impl Default for UnsafePolicy {
+    fn default() -> Self {
+        UnsafePolicy::AllFunctionsSafe
+    }
+}
+
+impl UnsafePolicy {
+    /// Marks functions as unsafe according to this policy. Functions
+    /// matching `unsafe_fns` or `safe_fns` override the policy, with
+    /// `unsafe_fns` winning if a function matches both. The policy alone
+    /// never removes an `unsafe` which bindgen already put there. It's an
+    /// error for an override to match no functions at all.
+    pub(crate) fn apply(
+        self,
+        bridge: &mut ItemMod,
+        unsafe_fns: &[FnPattern],
+        safe_fns: &[FnPattern],
+    ) -> Result<()> {
+        if let Some(glob) = find_unmatched(bridge, unsafe_fns) {
+            return Err(Error::UnsafeFnTargetNotFound(glob.to_string()));
+        }
+        if let Some(glob) = find_unmatched(bridge, safe_fns) {
+            return Err(Error::SafeFnTargetNotFound(glob.to_string()));
+        }
+        let aliases = type_aliases(bridge);
+        for f in foreign_fns_mut(bridge) {
+            let name = f.sig.ident.to_string();
+            let matches = |patterns: &[FnPattern]| patterns.iter().any(|p| p.is_match(&name));
+            if matches(unsafe_fns) {
+                f.sig.unsafety = Some(Default::default());
+            } else if matches(safe_fns) {
+                f.sig.unsafety = None;
+            } else if self.requires_unsafe(f, &aliases) {
+                f.sig.unsafety = Some(Default::default());
+            }
+        }
+        Ok(())
+    }
+
+    fn requires_unsafe(self, f: &ForeignItemFn, aliases: &HashMap<Ident, Type>) -> bool {
+        match self {
+            UnsafePolicy::AllFunctionsSafe => false,
+            UnsafePolicy::AllFunctionsUnsafe => true,
+            UnsafePolicy::RawPointersUnsafe => has_raw_pointers_or_unbound_refs(f, aliases),
+        }
+    }
+}
+
+/// Type aliases declared in the bridge module, which is how bindgen
+/// represents typedefs.
+fn type_aliases(bridge: &ItemMod) -> HashMap<Ident, Type> {
+    bridge
+        .content
+        .iter()
+        .flat_map(|(_, items)| items.iter())
+        .filter_map(|item| match item {
+            Item::Type(t) => Some((t.ident.clone(), (*t.ty).clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has_raw_pointers_or_unbound_refs(f: &ForeignItemFn, aliases: &HashMap<Ident, Type>) -> bool {
+    let mut takes_refs = false;
+    for input in &f.sig.inputs {
+        match input {
+            FnArg::Receiver(r) => takes_refs |= r.reference.is_some(),
+            FnArg::Typed(pt) => {
+                if has_raw_pointers(&pt.ty, aliases) {
+                    return true;
+                }
+                takes_refs |= is_borrow(&pt.ty);
+            }
+        }
+    }
+    match &f.sig.output {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ty) => match **ty {
+            Type::Reference(_) => !takes_refs,
+            ref ty => has_raw_pointers(ty, aliases),
+        },
+    }
+}
+
+/// Whether a type is or contains a raw pointer or function pointer.
+/// This looks through typedefs, and into generic arguments, since
+/// bindgen wraps function pointers in `Option`.
+fn has_raw_pointers(ty: &Type, aliases: &HashMap<Ident, Type>) -> bool {
+    match ty {
+        Type::Ptr(_) | Type::BareFn(_) => true,
+        Type::Reference(r) => has_raw_pointers(&r.elem, aliases),
+        Type::Slice(s) => has_raw_pointers(&s.elem, aliases),
+        Type::Array(a) => has_raw_pointers(&a.elem, aliases),
+        Type::Paren(p) => has_raw_pointers(&p.elem, aliases),
+        Type::Group(g) => has_raw_pointers(&g.elem, aliases),
+        Type::Tuple(t) => t.elems.iter().any(|elem| has_raw_pointers(elem, aliases)),
+        Type::Path(tp) => {
+            if let Some(target) = tp.path.get_ident().and_then(|id| aliases.get(id)) {
+                return has_raw_pointers(target, aliases);
+            }
+            tp.path.segments.iter().any(|seg| match &seg.arguments {
+                PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| match arg {
+                    GenericArgument::Type(ty) => has_raw_pointers(ty, aliases),
+                    _ => false,
+                }),
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Whether a parameter of this type can lend its lifetime to a returned
+/// reference. cxx spells a mutable `self` as `self: Pin<&mut T>`.
+fn is_borrow(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(_) => true,
+        Type::Path(tp) => match tp.path.segments.last() {
+            Some(seg) if seg.ident == "Pin" => match &seg.arguments {
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .any(|arg| matches!(arg, GenericArgument::Type(Type::Reference(_)))),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
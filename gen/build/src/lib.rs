@@ -56,6 +56,7 @@ pub enum Error {
 /// to specify the path for finding header files.
 pub struct Builder {
     build: cc::Build,
+    headers: Vec<Vec<u8>>,
     _tdir: TempDir,
 }
 
@@ -75,6 +76,7 @@ impl Builder {
         // However, the use of cc::Build is unique to gen/build.
         let source = syn::parse_file(&source).map_err(Error::Syntax)?;
         let mut counter = 0;
+        let mut headers = Vec::new();
         for item in source.items {
             if let Item::Macro(mac) = item {
                 if mac.mac.path.is_ident("include_cxx") {
@@ -88,9 +90,11 @@ impl Builder {
                         .map_err(Error::InvalidCxx)?;
                     let fname = format!("gen{}.cxx", counter);
                     counter += 1;
-                    let gen_cxx_path = Self::write_to_file(&tdir, &fname, &generated_code.cxx)
-                        .map_err(Error::FileWriteFail)?;
+                    let gen_cxx_path =
+                        Self::write_to_file(tdir.path(), &fname, &generated_code.cxx)
+                            .map_err(Error::FileWriteFail)?;
                     builder.file(gen_cxx_path);
+                    headers.push(generated_code.header);
                 }
             }
         }
@@ -99,6 +103,7 @@ impl Builder {
         } else {
             Ok(Builder {
                 build: builder,
+                headers,
                 _tdir: tdir,
             })
         }
@@ -109,8 +114,22 @@ impl Builder {
         &mut self.build
     }
 
-    fn write_to_file(tdir: &TempDir, filename: &str, content: &[u8]) -> std::io::Result<PathBuf> {
-        let path = tdir.path().join(filename);
+    /// Write the C++ header generated for each `include_cxx` macro into
+    /// `dir`, as `gen0.h`, `gen1.h` etc. in the order the macros appear.
+    /// C++ code elsewhere can then be built against these.
+    pub fn write_headers(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+        self.headers
+            .iter()
+            .enumerate()
+            .map(|(counter, header)| {
+                let fname = format!("gen{}.h", counter);
+                Self::write_to_file(dir.as_ref(), &fname, header).map_err(Error::FileWriteFail)
+            })
+            .collect()
+    }
+
+    fn write_to_file(dir: &Path, filename: &str, content: &[u8]) -> std::io::Result<PathBuf> {
+        let path = dir.join(filename);
         let mut f = File::create(&path)?;
         f.write_all(content)?;
         Ok(path)
@@ -38,6 +38,24 @@ mod tests {
     }
 
     fn run_test(cxx_code: &str, header_code: &str, rust_code: TokenStream, allowed_funcs: &[&str]) {
+        run_test_ex(
+            cxx_code,
+            header_code,
+            rust_code,
+            allowed_funcs,
+            TokenStream::new(),
+        )
+    }
+
+    /// Like `run_test`, but passes additional directives into the
+    /// `include_cxx!` macro.
+    fn run_test_ex(
+        cxx_code: &str,
+        header_code: &str,
+        rust_code: TokenStream,
+        allowed_funcs: &[&str],
+        extra_directives: TokenStream,
+    ) {
         // Step 1: Write the C++ header snippet to a temp file
         let tdir = tempdir().unwrap();
         write_to_file(&tdir, "input.h", header_code);
@@ -53,7 +71,8 @@ mod tests {
 
             include_cxx!(
                 Header("input.h"),
-                #(#allowed_funcs),*
+                #(#allowed_funcs,)*
+                #extra_directives
             );
 
             fn main() {
@@ -501,15 +520,863 @@ mod tests {
         run_test(cxx, hdr, rs, &["Bob"]);
     }
 
+    #[test]
+    fn test_unsafe_policy_raw_pointers() {
+        let cxx = indoc! {"
+            uint32_t give_int() {
+                return 4;
+            }
+            uint32_t deref_int(const uint32_t* a) {
+                return *a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            uint32_t give_int();
+            uint32_t deref_int(const uint32_t* a);
+        "};
+        let rs = quote! {
+            let a = ffi::give_int();
+            assert_eq!(unsafe { ffi::deref_int(&a) }, 4);
+        };
+        let directives = quote! {
+            UnsafePolicy("raw_pointers"),
+        };
+        run_test_ex(cxx, hdr, rs, &["give_int", "deref_int"], directives);
+    }
+
+    #[test]
+    fn test_method_prefix() {
+        let cxx = indoc! {"
+            void widget_set_a(Widget& w, uint32_t a) {
+                w.a = a;
+            }
+            uint32_t widget_get_a(const Widget& w) {
+                return w.a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            struct Widget {
+                uint32_t a;
+            };
+            void widget_set_a(Widget& w, uint32_t a);
+            uint32_t widget_get_a(const Widget& w);
+        "};
+        let rs = quote! {
+            let mut w = ffi::Widget { a: 1 };
+            w.set_a(4);
+            assert_eq!(w.get_a(), 4);
+        };
+        let directives = quote! {
+            MethodPrefix("Widget", "widget_"),
+        };
+        run_test_ex(
+            cxx,
+            hdr,
+            rs,
+            &["Widget", "widget_set_a", "widget_get_a"],
+            directives,
+        );
+    }
+
+    #[test]
+    fn test_deleted_and_private_special_members() {
+        let cxx = indoc! {"
+            Bob::Bob() : a(3) {
+            }
+            Fred::~Fred() {
+            }
+            uint32_t give_int() {
+                return 4;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            class Bob {
+            public:
+                Bob();
+                Bob(const Bob&) = delete;
+                Bob(Bob&&) = delete;
+                uint32_t a;
+            };
+            class Fred {
+            private:
+                ~Fred();
+            };
+            uint32_t give_int();
+        "};
+        // Bob can't be copied or moved, and Fred can't be destroyed, but
+        // neither should stop the generated C++ from compiling.
+        let rs = quote! {
+            let a = ffi::Bob::make_unique();
+            assert_eq!(a.as_ref().unwrap().a, 3);
+            assert_eq!(ffi::give_int(), 4);
+        };
+        run_test(cxx, hdr, rs, &["Bob", "Fred", "give_int"]);
+    }
+
+    #[test]
+    fn test_catch_exceptions() {
+        let cxx = indoc! {"
+            uint32_t might_throw(bool do_throw) {
+                if (do_throw) {
+                    throw std::runtime_error(\"oops\");
+                }
+                return 4;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            #include <stdexcept>
+            uint32_t might_throw(bool do_throw);
+        "};
+        let rs = quote! {
+            assert_eq!(ffi::might_throw(false).unwrap(), 4);
+            assert_eq!(ffi::might_throw(true).unwrap_err().what(), "oops");
+        };
+        let directives = quote! {
+            CatchExceptions("might_throw"),
+        };
+        run_test_ex(cxx, hdr, rs, &["might_throw"], directives);
+    }
+
+    #[test]
+    fn test_template_with_non_type_param() {
+        let cxx = indoc! {"
+            FourInts make_four() {
+                FourInts a;
+                for (size_t i = 0; i < 4; i++) {
+                    a.items[i] = i + 1;
+                }
+                return a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstddef>
+            #include <cstdint>
+            template <typename T, size_t N>
+            struct FixedVector {
+                T items[N];
+            };
+            typedef FixedVector<uint32_t, 4> FourInts;
+            FourInts make_four();
+        "};
+        let rs = quote! {
+            assert_eq!(ffi::make_four().items[3], 4);
+        };
+        run_test(cxx, hdr, rs, &["make_four", "FourInts"]);
+    }
+
+    #[test]
+    fn test_template_with_default_param() {
+        let cxx = indoc! {"
+            uint32_t vec_len(const IntVec& a) {
+                return a.len;
+            }
+            IntVec make_vec() {
+                IntVec a;
+                a.len = 3;
+                return a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            struct DefaultTag {};
+            template <typename T, typename Tag = DefaultTag>
+            struct MyVec {
+                uint32_t len;
+            };
+            typedef MyVec<uint32_t> IntVec;
+            uint32_t vec_len(const IntVec& a);
+            IntVec make_vec();
+        "};
+        let rs = quote! {
+            let a = ffi::make_vec();
+            assert_eq!(ffi::vec_len(&a), 3);
+        };
+        run_test(cxx, hdr, rs, &["vec_len", "make_vec", "IntVec"]);
+    }
+
+    #[test]
+    fn test_variadic_template_instantiation() {
+        let cxx = indoc! {"
+            IntAndDouble make_tuple() {
+                IntAndDouble a;
+                a.first = 3;
+                a.rest.first = 4.5;
+                return a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            template <typename... Ts>
+            struct Tuple;
+            template <>
+            struct Tuple<> {
+            };
+            template <typename T, typename... Ts>
+            struct Tuple<T, Ts...> {
+                T first;
+                Tuple<Ts...> rest;
+            };
+            typedef Tuple<uint32_t, double> IntAndDouble;
+            IntAndDouble make_tuple();
+        "};
+        let rs = quote! {
+            let a = ffi::make_tuple();
+            assert_eq!(a.first, 3);
+            assert_eq!(a.rest.first, 4.5);
+        };
+        run_test(cxx, hdr, rs, &["make_tuple", "IntAndDouble"]);
+    }
+
+    #[test]
+    fn test_template_specialization() {
+        let cxx = indoc! {"
+            SmallHolder make_holder() {
+                SmallHolder a;
+                a.value = 3;
+                a.is_small = true;
+                return a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            template <typename T>
+            struct Holder {
+                T value;
+            };
+            template <>
+            struct Holder<uint8_t> {
+                uint8_t value;
+                bool is_small;
+            };
+            typedef Holder<uint8_t> SmallHolder;
+            SmallHolder make_holder();
+        "};
+        // The specialization has a different layout from the primary
+        // template, and we must use the right one.
+        let rs = quote! {
+            let a = ffi::make_holder();
+            assert_eq!(a.value, 3);
+            assert!(a.is_small);
+        };
+        run_test(cxx, hdr, rs, &["make_holder", "SmallHolder"]);
+    }
+
+    #[test]
+    fn test_enum_integer_conversions() {
+        let cxx = indoc! {"
+            Color give_color() {
+                return Color::Blue;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            enum class Color : uint32_t {
+                Red,
+                Green,
+                Blue,
+            };
+            Color give_color();
+        "};
+        let rs = quote! {
+            use std::convert::TryFrom;
+            assert!(ffi::Color::try_from(1u32).unwrap() == ffi::Color::Green);
+            assert_eq!(ffi::Color::try_from(7u32).unwrap_err(), 7);
+            assert_eq!(u32::from(ffi::give_color()), 2);
+        };
+        run_test(cxx, hdr, rs, &["give_color", "Color"]);
+    }
+
+    #[test]
+    fn test_extra_attributes() {
+        let cxx = indoc! {"
+            uint32_t take_bob(Bob a) {
+                return a.a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            struct Bob {
+                uint32_t a;
+            };
+            uint32_t take_bob(Bob a);
+        "};
+        let rs = quote! {
+            let a = ffi::Bob { a: 12 };
+            let b = a;
+            assert_eq!(ffi::take_bob(a), ffi::take_bob(b));
+        };
+        let directives = quote! {
+            Attr("Bob", "#[derive(Clone, Copy)]"),
+        };
+        run_test_ex(cxx, hdr, rs, &["take_bob", "Bob"], directives);
+    }
+
+    #[test]
+    fn test_mutually_referential_types() {
+        let cxx = indoc! {"
+            A::A() : a(3), b(new B) {
+                b->parent = this;
+                b->b = 4;
+            }
+            A::~A() {
+            }
+            B::B() : parent(nullptr), b(0) {
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            #include <memory>
+            class B;
+            class A {
+            public:
+                A();
+                ~A();
+                uint32_t a;
+                std::unique_ptr<B> b;
+            };
+            class B {
+            public:
+                B();
+                const A* parent;
+                uint32_t b;
+            };
+        "};
+        let rs = quote! {
+            let a = ffi::A::make_unique();
+            assert_eq!(a.as_ref().unwrap().a, 3);
+            assert_eq!(a.as_ref().unwrap().b.as_ref().unwrap().b, 4);
+            let b = ffi::B::make_unique();
+            assert_eq!(b.as_ref().unwrap().b, 0);
+        };
+        run_test(cxx, hdr, rs, &["A", "B"]);
+    }
+
+    #[test]
+    fn test_mod_name() {
+        let cxx = indoc! {"
+            uint32_t give_int() {
+                return 4;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            uint32_t give_int();
+        "};
+        let rs = quote! {
+            assert_eq!(sys::give_int(), 4);
+        };
+        let directives = quote! {
+            ModName("sys"),
+        };
+        run_test_ex(cxx, hdr, rs, &["give_int"], directives);
+    }
+
+    #[test]
+    fn test_bitflags_enum() {
+        let cxx = indoc! {"
+            uint32_t take_perms(uint32_t a) {
+                return a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            enum Perms {
+                Read = 1,
+                Write = 2,
+            };
+            uint32_t take_perms(uint32_t a);
+        "};
+        // Only two values, so these wouldn't be spotted as flags without
+        // the directive.
+        let rs = quote! {
+            let a = ffi::Perms::Read | ffi::Perms::Write;
+            assert!(a.contains(ffi::Perms::Write));
+            assert!(!ffi::Perms::Read.contains(a));
+            assert_eq!(ffi::take_perms(a.into()), 3);
+        };
+        let directives = quote! {
+            Bitflags("Perms"),
+        };
+        run_test_ex(cxx, hdr, rs, &["take_perms", "Perms"], directives);
+    }
+
+    #[test]
+    fn test_c_variadic_function() {
+        let cxx = indoc! {"
+            uint32_t sum_ints(uint32_t count, ...) {
+                va_list args;
+                va_start(args, count);
+                uint32_t total = 0;
+                for (uint32_t i = 0; i < count; i++) {
+                    total += va_arg(args, uint32_t);
+                }
+                va_end(args);
+                return total;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdarg>
+            #include <cstdint>
+            uint32_t sum_ints(uint32_t count, ...);
+        "};
+        let rs = quote! {
+            assert_eq!(unsafe { ffi::sum_ints(2, 3u32, 4u32) }, 7);
+        };
+        run_test(cxx, hdr, rs, &["sum_ints"]);
+    }
+
+    #[test]
+    fn test_define_constants() {
+        let cxx = indoc! {"
+        "};
+        let hdr = indoc! {"
+            #define BOB_LIMIT 12
+            #define BOB_NAME \"bob\"
+            #define BOB_UNWANTED 13
+        "};
+        let rs = quote! {
+            assert_eq!(ffi::BOB_LIMIT, 12);
+            assert_eq!(ffi::BOB_NAME, "bob");
+        };
+        let directives = quote! {
+            AllowConst("BOB_LIMIT"),
+            AllowConst("BOB_NAME"),
+        };
+        run_test_ex(cxx, hdr, rs, &[], directives);
+    }
+
+    #[test]
+    fn test_volatile() {
+        let cxx = indoc! {"
+            uint32_t read_reg(const volatile uint32_t* reg) {
+                return *reg;
+            }
+            void write_reg(volatile uint32_t* reg, uint32_t value) {
+                *reg = value;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            uint32_t read_reg(const volatile uint32_t* reg);
+            void write_reg(volatile uint32_t* reg, uint32_t value);
+        "};
+        let rs = quote! {
+            let mut reg: u32 = 0;
+            unsafe {
+                ffi::write_reg(&mut reg, 3);
+                assert_eq!(ffi::read_reg(&reg), 3);
+            }
+        };
+        let directives = quote! {
+            UnsafePolicy("raw_pointers"),
+        };
+        run_test_ex(cxx, hdr, rs, &["read_reg", "write_reg"], directives);
+    }
+
+    #[test]
+    fn test_pod_with_array_fields() {
+        let cxx = indoc! {"
+            uint32_t take_bob(const Bob& a) {
+                return a.name[1] + a.mat[1][1];
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            struct Bob {
+                char name[4];
+                uint32_t mat[2][2];
+            };
+            uint32_t take_bob(const Bob& a);
+        "};
+        let rs = quote! {
+            let a = ffi::Bob {
+                name: [0, 2, 0, 0],
+                mat: [[0, 0], [0, 3]],
+            };
+            assert_eq!(ffi::take_bob(&a), 5);
+        };
+        run_test(cxx, hdr, rs, &["take_bob", "Bob"]);
+    }
+
+    #[test]
+    fn test_packed_and_aligned_pods() {
+        let cxx = indoc! {"
+            uint32_t take_packed(Packed a) {
+                return a.b;
+            }
+            uint32_t take_aligned(Aligned a) {
+                return a.a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            #pragma pack(push, 1)
+            struct Packed {
+                uint8_t a;
+                uint32_t b;
+            };
+            #pragma pack(pop)
+            struct alignas(16) Aligned {
+                uint32_t a;
+            };
+            uint32_t take_packed(Packed a);
+            uint32_t take_aligned(Aligned a);
+        "};
+        let rs = quote! {
+            assert_eq!(std::mem::size_of::<ffi::Packed>(), 5);
+            assert_eq!(std::mem::align_of::<ffi::Aligned>(), 16);
+            assert_eq!(ffi::take_packed(ffi::Packed { a: 1, b: 2 }), 2);
+            assert_eq!(ffi::take_aligned(ffi::Aligned { a: 3 }), 3);
+        };
+        run_test(cxx, hdr, rs, &["take_packed", "take_aligned", "Packed", "Aligned"]);
+    }
+
+    #[test]
+    fn test_allow_glob() {
+        let cxx = indoc! {"
+            uint32_t give_int() {
+                return 4;
+            }
+            uint32_t give_other_int() {
+                return 5;
+            }
+            uint32_t take_int(uint32_t a) {
+                return a + 3;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            uint32_t give_int();
+            uint32_t give_other_int();
+            uint32_t take_int(uint32_t a);
+        "};
+        let rs = quote! {
+            assert_eq!(ffi::give_int(), 4);
+            assert_eq!(ffi::give_other_int(), 5);
+            assert_eq!(ffi::take_int(3), 6);
+        };
+        let directives = quote! {
+            AllowGlob("give_*"),
+        };
+        run_test_ex(cxx, hdr, rs, &["take_(int|str)"], directives);
+    }
+
+    #[test]
+    fn test_block_method() {
+        let cxx = indoc! {"
+            Bob::Bob() : a(3) {
+            }
+            uint32_t Bob::get() const {
+                return a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            class Bob {
+            public:
+                Bob();
+                uint32_t get() const;
+                template <typename... Ts>
+                void log(Ts... args) const {
+                }
+                void unwanted(long double a) const {
+                }
+            private:
+                uint32_t a;
+            };
+        "};
+        // The rest of Bob should be unaffected by the blocked method.
+        let rs = quote! {
+            let a = ffi::Bob::make_unique();
+            assert_eq!(a.as_ref().unwrap().get(), 3);
+        };
+        let directives = quote! {
+            Block("Bob::unwanted"),
+            Block("Bob::log"),
+        };
+        run_test_ex(cxx, hdr, rs, &["Bob"], directives);
+    }
+
+    #[test]
+    fn test_rename_function() {
+        let cxx = indoc! {"
+            uint32_t GiveInt_v2() {
+                return 4;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            uint32_t GiveInt_v2();
+        "};
+        let rs = quote! {
+            assert_eq!(ffi::give_int(), 4);
+        };
+        let directives = quote! {
+            Rename("GiveInt_v2", "give_int"),
+        };
+        run_test_ex(cxx, hdr, rs, &["GiveInt_v2"], directives);
+    }
+
+    #[test]
+    fn test_unsafe_and_safe_fn_overrides() {
+        let cxx = indoc! {"
+            uint32_t give_int() {
+                return 4;
+            }
+            uint32_t give_other_int() {
+                return 5;
+            }
+            uint32_t add_ints(uint32_t a, uint32_t b) {
+                return a + b;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            uint32_t give_int();
+            uint32_t give_other_int();
+            uint32_t add_ints(uint32_t a, uint32_t b);
+        "};
+        let rs = quote! {
+            let a = unsafe { ffi::give_int() };
+            assert_eq!(ffi::give_other_int(), 5);
+            assert_eq!(ffi::add_ints(a, 1), 5);
+        };
+        let directives = quote! {
+            UnsafePolicy("all_unsafe"),
+            SafeFn("give_other_int"),
+            SafeFn("add_*"),
+        };
+        run_test_ex(
+            cxx,
+            hdr,
+            rs,
+            &["give_int", "give_other_int", "add_ints"],
+            directives,
+        );
+    }
+
+    #[test]
+    fn test_opaque_pod() {
+        let cxx = indoc! {"
+            std::unique_ptr<Bob> give_bob() {
+                auto a = std::make_unique<Bob>();
+                a->a = 3;
+                a->b = 4;
+                return a;
+            }
+            uint32_t get_b(const Bob& bob) {
+                return bob.b;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            #include <memory>
+            struct Bob {
+                uint32_t a;
+                uint32_t b;
+            };
+            std::unique_ptr<Bob> give_bob();
+            uint32_t get_b(const Bob& bob);
+        "};
+        // Bob's fields aren't visible from Rust, so we can only get at
+        // them through C++. Its size is still known, since bindgen puts
+        // an opaque blob of the same layout in place of the fields.
+        let rs = quote! {
+            let bob = ffi::give_bob();
+            let ffi::Bob { _bindgen_opaque_blob: _ } = bob.as_ref().unwrap();
+            assert_eq!(std::mem::size_of::<ffi::Bob>(), 8);
+            assert_eq!(ffi::get_b(bob.as_ref().unwrap()), 4);
+        };
+        let directives = quote! {
+            Opaque("Bob"),
+        };
+        run_test_ex(cxx, hdr, rs, &["give_bob", "get_b", "Bob"], directives);
+    }
+
+    #[test]
+    fn test_method_prefix_leaves_non_identifiers() {
+        let cxx = indoc! {"
+            uint32_t widget_get_a(const Widget& w) {
+                return w.a;
+            }
+            uint32_t widget_2d_size(const Widget& w) {
+                return w.a * w.a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            struct Widget {
+                uint32_t a;
+            };
+            uint32_t widget_get_a(const Widget& w);
+            uint32_t widget_2d_size(const Widget& w);
+        "};
+        // 2d_size isn't a valid method name, so stays a free function.
+        let rs = quote! {
+            let w = ffi::Widget { a: 3 };
+            assert_eq!(w.get_a(), 3);
+            assert_eq!(ffi::widget_2d_size(&w), 9);
+        };
+        let directives = quote! {
+            MethodPrefix("Widget", "widget_"),
+        };
+        run_test_ex(
+            cxx,
+            hdr,
+            rs,
+            &["Widget", "widget_get_a", "widget_2d_size"],
+            directives,
+        );
+    }
+
+    #[test]
+    fn test_write_headers() {
+        let tdir = tempdir().unwrap();
+        write_to_file(
+            &tdir,
+            "input.h",
+            indoc! {"
+                #include <cstdint>
+                struct Bob {
+                    uint32_t a;
+                };
+                uint32_t take_bob(const Bob& a);
+            "},
+        );
+        let rs = quote! {
+            use autocxx_macro::include_cxx;
+
+            include_cxx!(
+                Header("input.h"),
+                Allow("take_bob"),
+                Allow("Bob"),
+            );
+        };
+        let rs_path = write_to_file(&tdir, "input.rs", &format!("{}", rs));
+        // C++ outside the crate builds against the header we write out,
+        // rather than anything buried in the build directory.
+        let cxx_path = write_to_file(
+            &tdir,
+            "consumer.cxx",
+            indoc! {"
+                #include \"gen0.h\"
+                uint32_t take_bob(const Bob& a) {
+                    return a.a;
+                }
+                uint32_t consume() {
+                    Bob b = { 3 };
+                    return take_bob(b);
+                }
+            "},
+        );
+        let target_dir = tdir.path().join("target");
+        std::fs::create_dir(&target_dir).unwrap();
+        let header_dir = tdir.path().join("include");
+        std::fs::create_dir(&header_dir).unwrap();
+        std::env::set_var("OUT_DIR", &target_dir);
+        std::env::set_var("AUTOCXX_INC", tdir.path());
+        let target = rust_info::get().target_triple.unwrap();
+        let mut b = autocxx_build::Builder::new(&rs_path).unwrap();
+        let headers = b.write_headers(&header_dir).unwrap();
+        assert_eq!(headers, vec![header_dir.join("gen0.h")]);
+        b.builder()
+            .file(cxx_path)
+            .host(&target)
+            .target(&target)
+            .opt_level(1)
+            .flag("-std=c++11")
+            .include(tdir.path())
+            .include(&header_dir)
+            .try_compile("autocxx-write-headers")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_method_prefix_catch_exceptions() {
+        let cxx = indoc! {"
+            uint32_t widget_checked_a(const Widget& w) {
+                if (w.a == 0) {
+                    throw std::runtime_error(\"empty\");
+                }
+                return w.a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            #include <stdexcept>
+            struct Widget {
+                uint32_t a;
+            };
+            uint32_t widget_checked_a(const Widget& w);
+        "};
+        let rs = quote! {
+            assert_eq!(ffi::Widget { a: 3 }.checked_a().unwrap(), 3);
+            assert_eq!(ffi::Widget { a: 0 }.checked_a().unwrap_err().what(), "empty");
+        };
+        let directives = quote! {
+            MethodPrefix("Widget", "widget_"),
+            CatchExceptions("widget_checked_a"),
+        };
+        run_test_ex(cxx, hdr, rs, &["Widget", "widget_checked_a"], directives);
+    }
+
+    #[test]
+    fn test_rename_with_method_prefix() {
+        let cxx = indoc! {"
+            uint32_t widget_get_a(const Widget& w) {
+                return w.a;
+            }
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            struct Widget {
+                uint32_t a;
+            };
+            uint32_t widget_get_a(const Widget& w);
+        "};
+        // The prefix applies to the C++ name, not the new one.
+        let rs = quote! {
+            let w = ffi::Widget { a: 3 };
+            assert_eq!(w.get_a(), 3);
+            assert_eq!(ffi::get_widget_a(&w), 3);
+        };
+        let directives = quote! {
+            MethodPrefix("Widget", "widget_"),
+            Rename("widget_get_a", "get_widget_a"),
+        };
+        run_test_ex(cxx, hdr, rs, &["Widget", "widget_get_a"], directives);
+    }
+
+    #[test]
+    fn test_allow_const_skips_globals() {
+        let cxx = indoc! {"
+            uint32_t BOB_COUNT = 3;
+        "};
+        let hdr = indoc! {"
+            #include <cstdint>
+            #define BOB_LIMIT 12
+            extern uint32_t BOB_COUNT;
+        "};
+        // BOB_COUNT matches too, but isn't a constant.
+        let rs = quote! {
+            assert_eq!(ffi::BOB_LIMIT, 12);
+        };
+        let directives = quote! {
+            AllowConst("BOB_*"),
+        };
+        run_test_ex(cxx, hdr, rs, &[], directives);
+    }
+
     // Yet to test:
     // 1. Make UniquePtr<CxxStrings> in Rust
-    // 2. Enums
-    // 3. Constants
-    // 4. Call methods
-    // 5. Templated stuff
-    // 6. Preprocessor directives
-    // 7. Out params
-    // 8. Opaque type handling
+    // 2. Call methods
+    // 3. Templated stuff
+    // 4. Preprocessor directives
+    // 5. Out params
     // Stuff which requires much more thought:
     // 1. Shared pointers
     // Negative tests: